@@ -0,0 +1,149 @@
+use core::fmt;
+use std::{
+    error::Error,
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream, UdpSocket},
+    time::Duration,
+};
+
+use crate::structs::message::Message;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+const DEFAULT_RETRIES: u32 = 3;
+
+/// The UDP payload size this client advertises via EDNS0 (see
+/// `Message::with_edns0`), and the size the receive buffer is sized to
+/// match. Without EDNS0 a response is capped at 512 bytes; this lets a
+/// server reply with larger record sets over UDP instead of truncating.
+pub const EDNS0_UDP_PAYLOAD_SIZE: u16 = 4096;
+
+#[derive(Debug)]
+pub struct ResolveError {
+    pub value: String,
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to resolve query: {}", self.value)
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// Which transport a query is allowed to use. `Auto` is right for almost
+/// everything; `Tcp` is for callers that already know the answer won't fit
+/// in a UDP datagram (e.g. AXFR, or a TXT record expected to be large) and
+/// would rather skip the doomed UDP round-trip entirely.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Transport {
+    Udp,
+    Tcp,
+    #[default]
+    Auto,
+}
+
+/// Sends a `Message` to a resolver, automatically retrying on
+/// timeout/ID-mismatch and, under `Transport::Auto`, falling back to TCP
+/// when the UDP response comes back truncated.
+pub struct Resolver {
+    timeout: Duration,
+    retries: u32,
+    transport: Transport,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            transport: Transport::Auto,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub fn query(&self, server: SocketAddr, msg: &Message) -> Result<Message, Box<dyn Error>> {
+        match self.transport {
+            Transport::Udp => self.query_udp(server, msg),
+            Transport::Tcp => self.query_tcp(server, msg),
+            Transport::Auto => {
+                let response = self.query_udp(server, msg)?;
+
+                if response.header.is_truncated() {
+                    return self.query_tcp(server, msg);
+                }
+
+                Ok(response)
+            }
+        }
+    }
+
+    fn query_udp(&self, server: SocketAddr, msg: &Message) -> Result<Message, Box<dyn Error>> {
+        let packet = msg.to_bytes();
+
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_read_timeout(Some(self.timeout))?;
+
+        let mut last_err: Option<Box<dyn Error>> = None;
+
+        for _ in 0..=self.retries {
+            socket.send_to(&packet, server)?;
+
+            let mut buf = [0u8; EDNS0_UDP_PAYLOAD_SIZE as usize];
+            match socket.recv_from(&mut buf) {
+                Ok((len, _)) => match Message::from_bytes(&buf, len) {
+                    Ok(response) if response.header.id == msg.header.id => return Ok(response),
+                    Ok(_) => continue, // stray reply for a different query id
+                    Err(e) => last_err = Some(e),
+                },
+                Err(e) => last_err = Some(Box::new(e)),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            Box::new(ResolveError {
+                value: "no response received from server".to_string(),
+            })
+        }))
+    }
+
+    fn query_tcp(&self, server: SocketAddr, msg: &Message) -> Result<Message, Box<dyn Error>> {
+        let packet = msg.to_bytes();
+
+        let mut stream = TcpStream::connect(server)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let len = u16::try_from(packet.len())?;
+        stream.write_all(&len.to_be_bytes())?;
+        stream.write_all(&packet)?;
+
+        let mut len_buf = [0u8; 2];
+        stream.read_exact(&mut len_buf)?;
+        let resp_len = u16::from_be_bytes(len_buf) as usize;
+
+        let mut resp_buf = vec![0u8; resp_len];
+        stream.read_exact(&mut resp_buf)?;
+
+        Message::from_bytes(&resp_buf, resp_len)
+    }
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
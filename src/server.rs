@@ -0,0 +1,144 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    net::{Ipv4Addr, Ipv6Addr, UdpSocket},
+    str::FromStr,
+};
+
+use crate::structs::{
+    answer::{Answer, DomainNameRData, IpRData, Ipv6RData, MxRData, RData, TxtRData},
+    header::Rcode,
+    message::Message,
+    question::{QClass, QType},
+};
+
+/// Looks records up by the owner name (lowercased, no trailing dot) and the
+/// numeric query type being asked about.
+pub type ZoneKey = (String, u16);
+pub type Zone = HashMap<ZoneKey, Vec<Answer>>;
+
+/// Loads a zone from a simple text file, one record per line:
+///
+/// ```text
+/// example.com.   A     300 93.184.216.34
+/// example.com.   MX    300 10 mail.example.com.
+/// mail.example.com. A  300 93.184.216.34
+/// # this is a comment
+/// ```
+///
+/// Blank lines and lines starting with `#` are ignored. Supported record
+/// types are A, AAAA, CNAME, NS, PTR, MX and TXT; any other type is
+/// rejected with an error. Everything is class IN.
+pub fn load_zone(path: &str) -> Result<Zone, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+    let mut zone = Zone::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, answer) = parse_zone_line(line)?;
+        zone.entry(key).or_default().push(answer);
+    }
+
+    Ok(zone)
+}
+
+fn parse_zone_line(line: &str) -> Result<(ZoneKey, Answer), Box<dyn Error>> {
+    let mut fields = line.split_whitespace();
+
+    let name = fields.next().ok_or("zone line missing NAME")?;
+    let rtype = QType::from_str(fields.next().ok_or("zone line missing TYPE")?)?;
+    let ttl: u32 = fields.next().ok_or("zone line missing TTL")?.parse()?;
+
+    let rdata: Box<dyn RData> = match rtype {
+        QType::A => {
+            let addr: Ipv4Addr = fields.next().ok_or("A record missing address")?.parse()?;
+            Box::new(IpRData::new(addr))
+        }
+
+        QType::AAAA => {
+            let addr: Ipv6Addr = fields.next().ok_or("AAAA record missing address")?.parse()?;
+            Box::new(Ipv6RData::new(addr))
+        }
+
+        QType::CNAME | QType::NS | QType::PTR => {
+            let target = fields.next().ok_or("record missing target name")?;
+            Box::new(DomainNameRData::new(target.to_string()))
+        }
+
+        QType::MX => {
+            let preference: u16 = fields.next().ok_or("MX record missing preference")?.parse()?;
+            let exchange = fields.next().ok_or("MX record missing exchange")?;
+            Box::new(MxRData::new(preference, exchange.to_string()))
+        }
+
+        QType::TXT => {
+            let value: Vec<&str> = fields.collect();
+            if value.is_empty() {
+                return Err("TXT record missing value".into());
+            }
+            Box::new(TxtRData::new(vec![value.join(" ")]))
+        }
+
+        other => return Err(format!("unsupported zone record type {other:?}").into()),
+    };
+
+    let name = if name.ends_with('.') {
+        name.to_string()
+    } else {
+        format!("{name}.")
+    };
+
+    let key = zone_key(&name, rtype.to_u16());
+    let answer = Answer::new(name, rtype, QClass::IN, ttl, rdata);
+
+    Ok((key, answer))
+}
+
+/// A minimal authoritative DNS server: answers queries straight out of an
+/// in-memory zone instead of recursing or forwarding anywhere. Intended for
+/// local testing, not as a production-grade nameserver.
+pub fn serve(bind_addr: &str, zone: &Zone) -> Result<(), Box<dyn Error>> {
+    let socket = UdpSocket::bind(bind_addr)?;
+
+    loop {
+        let mut buf = [0u8; 512];
+        let (len, peer) = socket.recv_from(&mut buf)?;
+
+        let query = match Message::from_bytes(&buf, len) {
+            Ok(query) => query,
+            Err(_) => continue, // drop anything we can't even parse
+        };
+
+        let response = build_response(&query, zone);
+        socket.send_to(&response.to_bytes(), peer)?;
+    }
+}
+
+fn zone_key(name: &str, qtype: u16) -> ZoneKey {
+    (name.trim_end_matches('.').to_lowercase(), qtype)
+}
+
+fn build_response(query: &Message, zone: &Zone) -> Message {
+    let answers: Vec<Answer> = query
+        .question()
+        .iter()
+        .flat_map(|q| {
+            let key = zone_key(q.qname(), q.qtype().to_u16());
+            zone.get(&key).into_iter().flatten().cloned()
+        })
+        .collect();
+
+    let rcode = if answers.is_empty() {
+        Rcode::NXDomain
+    } else {
+        Rcode::NoError
+    }
+    .to_u8();
+
+    Message::create_response(query, answers, true, rcode)
+}
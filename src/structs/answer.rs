@@ -1,184 +1,635 @@
 use core::fmt;
-use std::{collections::HashMap, error::Error};
-
-use bytes::Buf;
+use std::{
+    error::Error,
+    net::{Ipv4Addr, Ipv6Addr},
+};
 
 use crate::structs::{
-    RefNode,
+    BytePacketBuffer, NameCompressor,
     question::{QClass, QType},
     read_label,
 };
 
-#[derive(Debug, Clone)]
-struct DomainName(String);
+/// A parsed (or raw) RDATA payload.
+///
+/// Concrete record types implement this directly instead of being variants
+/// of a central enum; `read_rdata` picks which one to parse by record type,
+/// so a new type needs its own struct plus an arm in that match.
+pub trait RData: fmt::Display {
+    fn to_bytes(&self, buf: &mut Vec<u8>);
+
+    /// Needed so `Box<dyn RData>` can implement `Clone` below; trait
+    /// objects can't derive `Clone` directly.
+    fn clone_box(&self) -> Box<dyn RData>;
+
+    /// Lets a caller downcast back to the concrete type (e.g. `IpRData`) to
+    /// pull a typed value like an `Ipv4Addr` out of a `Box<dyn RData>`
+    /// instead of only being able to `Display` it.
+    fn as_any(&self) -> &dyn std::any::Any;
+}
 
-impl From<String> for DomainName {
-    fn from(s: String) -> Self {
-        DomainName(s)
+impl Clone for Box<dyn RData> {
+    fn clone(&self) -> Self {
+        self.clone_box()
     }
 }
 
-impl fmt::Display for DomainName {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+#[derive(Clone)]
+pub struct IpRData(Ipv4Addr);
+
+impl IpRData {
+    pub fn new(addr: Ipv4Addr) -> Self {
+        Self(addr)
+    }
+
+    pub fn addr(&self) -> Ipv4Addr {
+        self.0
+    }
+}
+
+impl fmt::Display for IpRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl RData for IpRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.0.octets());
+    }
+
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[derive(Clone)]
+pub struct Ipv6RData(Ipv6Addr);
+
+impl Ipv6RData {
+    pub fn new(addr: Ipv6Addr) -> Self {
+        Self(addr)
+    }
+}
+
+impl fmt::Display for Ipv6RData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl RData for Ipv6RData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.0.octets());
+    }
+
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// RDATA for record types whose value is a single domain name (CNAME, NS, PTR).
+#[derive(Clone)]
+pub struct DomainNameRData(String);
+
+impl DomainNameRData {
+    pub fn new(name: String) -> Self {
+        Self(name)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for DomainNameRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
-#[derive(Debug)]
+impl RData for DomainNameRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        write_qname(buf, &self.0);
+    }
+
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// A TXT record can pack several `<len><bytes>` character-strings back to
+/// back within a single RDATA (e.g. long SPF/DKIM entries); keep each one
+/// distinct rather than truncating to the first.
+#[derive(Clone)]
+pub struct TxtRData(Vec<String>);
+
+impl TxtRData {
+    pub fn new(strings: Vec<String>) -> Self {
+        Self(strings)
+    }
+}
+
+impl fmt::Display for TxtRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.join(" "))
+    }
+}
+
+impl RData for TxtRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        for s in &self.0 {
+            buf.push(s.len() as u8);
+            buf.extend_from_slice(s.as_bytes());
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[derive(Clone)]
+pub struct MxRData {
+    preference: u16,
+    exchange: String,
+}
+
+impl MxRData {
+    pub fn new(preference: u16, exchange: String) -> Self {
+        Self {
+            preference,
+            exchange,
+        }
+    }
+}
+
+impl fmt::Display for MxRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.exchange, self.preference)
+    }
+}
+
+impl RData for MxRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        write_u16(buf, self.preference);
+        write_qname(buf, &self.exchange);
+    }
+
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[derive(Clone)]
 #[allow(clippy::upper_case_acronyms)]
-enum RDATA {
-    DomainName(DomainName), // CNAME, NS, PTR
-    IPV4([u8; 4]),          // A
-    IPV6([u8; 16]),         // AAAA
-    TXT(String),            // TXT
-    MX(u16, String),        // MX
-    SOA(DomainName, DomainName, u32, u32, u32, u32, u32),
+pub struct SoaRData {
+    mname: String,
+    rname: String,
+    serial: u32,
+    refresh: u32,
+    retry: u32,
+    expire: u32,
+    minimum: u32,
 }
 
-impl fmt::Display for RDATA {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::DomainName(s) => {
-                writeln!(f, "{s}")?;
-            }
+impl fmt::Display for SoaRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f)?;
+        writeln!(f, "MNAME: {}", self.mname)?;
+        writeln!(f, "RNAME: {}", self.rname)?;
+        writeln!(f, "SERIAL: {}", self.serial)?;
+        writeln!(f, "REFRESH: {}", self.refresh)?;
+        writeln!(f, "RETRY: {}", self.retry)?;
+        writeln!(f, "EXPIRE: {}", self.expire)?;
+        write!(f, "MINIMUM: {}", self.minimum)
+    }
+}
 
-            Self::TXT(s) => {
-                writeln!(f, "{s}")?;
-            }
+impl RData for SoaRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        write_qname(buf, &self.mname);
+        write_qname(buf, &self.rname);
+        buf.extend_from_slice(&self.serial.to_be_bytes());
+        buf.extend_from_slice(&self.refresh.to_be_bytes());
+        buf.extend_from_slice(&self.retry.to_be_bytes());
+        buf.extend_from_slice(&self.expire.to_be_bytes());
+        buf.extend_from_slice(&self.minimum.to_be_bytes());
+    }
 
-            Self::MX(pref, s) => {
-                writeln!(f, "{s} ({pref})")?;
-            }
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
 
-            Self::IPV4(ip) => {
-                writeln!(f, "{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3])?;
-            }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
 
-            Self::IPV6(ip) => {
-                for i in 0..8 {
-                    if i != 0 {
-                        write!(f, ":")?;
-                    }
-                    let segment = ((ip[2 * i] as u16) << 8) | ip[2 * i + 1] as u16;
-                    write!(f, "{:x}", segment)?;
-                }
-            }
+#[derive(Clone)]
+pub struct SrvRData {
+    priority: u16,
+    weight: u16,
+    port: u16,
+    target: String,
+}
 
-            Self::SOA(mname, rname, serial, refresh, retry, expire, minimum) => {
-                writeln!(f)?;
-                writeln!(f, "MNAME: {mname}")?;
-                writeln!(f, "RNAME: {rname}")?;
-                writeln!(f, "SERIAL: {serial}")?;
-                writeln!(f, "REFRESH: {refresh}")?;
-                writeln!(f, "RETRY: {retry}")?;
-                writeln!(f, "EXPIRE: {expire}")?;
-                writeln!(f, "MINIMUM: {minimum}")?;
-            }
-        };
+impl fmt::Display for SrvRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.priority, self.weight, self.port, self.target
+        )
+    }
+}
 
-        Ok(())
+impl RData for SrvRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        write_u16(buf, self.priority);
+        write_u16(buf, self.weight);
+        write_u16(buf, self.port);
+        write_qname(buf, &self.target);
+    }
+
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
     }
 }
 
-pub struct Answer {
-    name: String,
-    rtype: QType,
-    class: QClass,
-    ttl: u32,
-    rdlength: u16,
-    rdata: RDATA,
+/// A CAA value fills the remainder of RDATA after the flags byte and tag,
+/// so it's kept as raw bytes rather than assumed to be a UTF-8 string.
+#[derive(Clone)]
+pub struct CaaRData {
+    flags: u8,
+    tag: String,
+    value: Vec<u8>,
 }
 
-impl Answer {
-    pub fn from_bytes(
-        buf: &mut &[u8],
-        len: usize,
-        nodes: &mut HashMap<usize, RefNode>,
-    ) -> Result<Self, Box<dyn Error>> {
-        let index = len - buf.remaining();
-
-        let qname = read_label(buf, index, nodes)?;
-        let rtype = QType::from_u16(buf.get_u16())?;
-
-        let class = QClass::from_u16(buf.get_u16())?;
-        let ttl = buf.get_u32();
-        let rdlength = buf.get_u16();
-
-        let rdata = match rtype {
-            QType::A => {
-                let mut v = Vec::new();
-                for _ in 0..rdlength {
-                    v.push(buf.get_u8());
-                }
-
-                assert_eq!(v.len(), 4, "Invalid A record length");
-                RDATA::IPV4([v[0], v[1], v[2], v[3]])
-            }
+impl fmt::Display for CaaRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} \"{}\"",
+            self.flags,
+            self.tag,
+            String::from_utf8_lossy(&self.value)
+        )
+    }
+}
 
-            QType::AAAA => {
-                let mut v = Vec::new();
-                for _ in 0..rdlength {
-                    v.push(buf.get_u8());
-                }
+impl RData for CaaRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        buf.push(self.flags);
+        buf.push(self.tag.len() as u8);
+        buf.extend_from_slice(self.tag.as_bytes());
+        buf.extend_from_slice(&self.value);
+    }
 
-                assert_eq!(v.len(), 16, "Invalid AAAA record length");
-                let mut addr = [0u8; 16];
-                addr.copy_from_slice(&v);
-                RDATA::IPV6(addr)
-            }
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
 
-            QType::TXT => {
-                let mut v = Vec::new();
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
 
-                let len = buf.get_u8();
-                for _ in 0..len {
-                    let c = buf.get_u8();
-                    v.push(c);
-                }
+/// RDATA for an EDNS0 OPT pseudo-record: a run of option code/length/value
+/// triples (e.g. NSID, cookies) rather than a single fixed-shape value.
+#[derive(Clone)]
+pub struct OptRData {
+    options: Vec<(u16, Vec<u8>)>,
+}
 
-                let s: String = v.iter().map(|&b| b as char).collect();
-                RDATA::TXT(s)
-            }
+impl OptRData {
+    pub fn new(options: Vec<(u16, Vec<u8>)>) -> Self {
+        Self { options }
+    }
+}
 
-            QType::CNAME | QType::NS => {
-                let index = len - buf.remaining();
-                let name = read_label(buf, index, nodes)?;
+impl fmt::Display for OptRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.options.is_empty() {
+            return write!(f, "<no options>");
+        }
 
-                RDATA::DomainName(DomainName::from(name))
-            }
+        let rendered: Vec<String> = self
+            .options
+            .iter()
+            .map(|(code, value)| format!("{code}={}", value.len()))
+            .collect();
+
+        write!(f, "{}", rendered.join(", "))
+    }
+}
+
+impl RData for OptRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        for (code, value) in &self.options {
+            write_u16(buf, *code);
+            write_u16(buf, value.len() as u16);
+            buf.extend_from_slice(value);
+        }
+    }
 
-            QType::MX => {
-                let pref = buf.get_u16();
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Fallback RDATA for a `QType` we don't parse into a typed struct. Keeps the
+/// bytes exactly as received so the record can still be displayed (and
+/// re-serialized) instead of the message failing to parse.
+#[derive(Clone)]
+pub struct RawRData {
+    rtype: u16,
+    bytes: Vec<u8>,
+}
+
+impl fmt::Display for RawRData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<{} bytes of unrecognized TYPE {} RDATA>",
+            self.bytes.len(),
+            self.rtype
+        )
+    }
+}
 
-                let index = len - buf.remaining();
-                let name = read_label(buf, index, nodes)?;
+impl RData for RawRData {
+    fn to_bytes(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.bytes);
+    }
 
-                RDATA::MX(pref, name)
+    fn clone_box(&self) -> Box<dyn RData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+fn write_u16(buf: &mut Vec<u8>, v: u16) {
+    buf.extend_from_slice(&v.to_be_bytes());
+}
+
+/// Writes a domain name as plain (uncompressed) labels terminated by a 0 byte.
+fn write_qname(buf: &mut Vec<u8>, name: &str) {
+    for label in name.trim_end_matches('.').split('.').filter(|l| !l.is_empty()) {
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+
+    buf.push(0);
+}
+
+/// Whether `rtype`'s RDATA can contain a domain name read via `read_label`,
+/// and so could carry a compression pointer aimed at an offset in whatever
+/// packet it was originally parsed from.
+fn rdata_embeds_a_name(rtype: QType) -> bool {
+    matches!(
+        rtype,
+        QType::CNAME | QType::NS | QType::PTR | QType::MX | QType::SOA | QType::SRV
+    )
+}
+
+fn read_rdata(
+    rtype: QType,
+    buf: &mut BytePacketBuffer,
+    rdlength: u16,
+) -> Result<Box<dyn RData>, Box<dyn Error>> {
+    let rdata_start = buf.pos();
+
+    Ok(match rtype {
+        QType::A => {
+            let v = buf.read_range(4)?;
+            Box::new(IpRData(Ipv4Addr::new(v[0], v[1], v[2], v[3])))
+        }
+
+        QType::AAAA => {
+            let v: [u8; 16] = buf.read_range(16)?.try_into().unwrap();
+            Box::new(Ipv6RData(Ipv6Addr::from(v)))
+        }
+
+        QType::TXT => {
+            // RDATA can pack several <len><bytes> character-strings back
+            // to back; keep reading until the whole RDLENGTH is consumed.
+            let mut strings = Vec::new();
+
+            while buf.pos() - rdata_start < rdlength as usize {
+                let str_len = buf.read_u8()?;
+                let bytes = buf.read_range(str_len as usize)?;
+                strings.push(bytes.iter().map(|&b| b as char).collect());
             }
 
-            QType::SOA => {
-                let index = len - buf.remaining();
-                let mname = read_label(buf, index, nodes)?;
-
-                let index = len - buf.remaining();
-                let rname = read_label(buf, index, nodes)?;
-
-                let serial = buf.get_u32();
-                let refresh = buf.get_u32();
-                let retry = buf.get_u32();
-                let expire = buf.get_u32();
-                let minimum = buf.get_u32();
-
-                RDATA::SOA(
-                    DomainName::from(mname),
-                    DomainName::from(rname),
-                    serial,
-                    refresh,
-                    retry,
-                    expire,
-                    minimum,
-                )
+            Box::new(TxtRData(strings))
+        }
+
+        QType::CNAME | QType::NS | QType::PTR => {
+            let name = read_label(buf)?;
+
+            Box::new(DomainNameRData(name))
+        }
+
+        QType::MX => {
+            let preference = buf.read_u16()?;
+            let exchange = read_label(buf)?;
+
+            Box::new(MxRData {
+                preference,
+                exchange,
+            })
+        }
+
+        QType::SOA => {
+            let mname = read_label(buf)?;
+            let rname = read_label(buf)?;
+
+            let serial = buf.read_u32()?;
+            let refresh = buf.read_u32()?;
+            let retry = buf.read_u32()?;
+            let expire = buf.read_u32()?;
+            let minimum = buf.read_u32()?;
+
+            Box::new(SoaRData {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            })
+        }
+
+        QType::SRV => {
+            let priority = buf.read_u16()?;
+            let weight = buf.read_u16()?;
+            let port = buf.read_u16()?;
+            let target = read_label(buf)?;
+
+            Box::new(SrvRData {
+                priority,
+                weight,
+                port,
+                target,
+            })
+        }
+
+        QType::CAA => {
+            let flags = buf.read_u8()?;
+            let tag_len = buf.read_u8()?;
+            let tag = buf
+                .read_range(tag_len as usize)?
+                .iter()
+                .map(|&b| b as char)
+                .collect();
+
+            let consumed = buf.pos() - rdata_start;
+            let value_len = (rdlength as usize).saturating_sub(consumed);
+            let value = buf.read_range(value_len)?.to_vec();
+
+            Box::new(CaaRData { flags, tag, value })
+        }
+
+        QType::OPT => {
+            let mut options = Vec::new();
+
+            while buf.pos() - rdata_start < rdlength as usize {
+                let code = buf.read_u16()?;
+                let len = buf.read_u16()?;
+                let value = buf.read_range(len as usize)?.to_vec();
+                options.push((code, value));
             }
+
+            Box::new(OptRData { options })
+        }
+
+        QType::Unknown(rtype) => {
+            let bytes = buf.read_range(rdlength as usize)?.to_vec();
+
+            Box::new(RawRData { rtype, bytes })
+        }
+    })
+}
+
+#[derive(Clone)]
+pub struct Answer {
+    name: String,
+    rtype: QType,
+    class: QClass,
+    ttl: u32,
+    rdlength: u16,
+    rdata: Box<dyn RData>,
+    /// The RDATA exactly as it appeared on the wire (or, for a record built
+    /// in-process, exactly as `rdata` serializes). Kept alongside the typed
+    /// `rdata` so a record can be round-tripped byte-for-byte even for
+    /// quirks the typed representation doesn't preserve (e.g. non-canonical
+    /// TXT segmenting). Exception: for RDATA that embeds a domain name
+    /// (NS/CNAME/PTR/MX/SOA/SRV), a wire capture could hold a compression
+    /// pointer relative to the *original* packet's offsets, which would
+    /// dangle if copied verbatim into a different message — `from_bytes`
+    /// re-derives `raw` from `rdata` (uncompressed) for those types instead.
+    raw: Vec<u8>,
+}
+
+impl Answer {
+    /// Builds an answer record to hand to a response, e.g. from a zone file
+    /// entry. `rdlength` is derived from `rdata` rather than taken as a
+    /// parameter, so it can never drift out of sync with the bytes it
+    /// describes.
+    pub fn new(name: String, rtype: QType, class: QClass, ttl: u32, rdata: Box<dyn RData>) -> Self {
+        let mut raw = Vec::new();
+        rdata.to_bytes(&mut raw);
+
+        Self {
+            name,
+            rtype,
+            class,
+            ttl,
+            rdlength: raw.len() as u16,
+            rdata,
+            raw,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rtype(&self) -> QType {
+        self.rtype
+    }
+
+    pub fn rdata(&self) -> &dyn RData {
+        self.rdata.as_ref()
+    }
+
+    /// Builds the EDNS0 OPT pseudo-record for the additional section (RFC
+    /// 6891): NAME is the root domain, CLASS doubles as the advertised UDP
+    /// payload size, and TTL packs the extended RCODE (always 0 here, since
+    /// it's only meaningful on a response), version, and DO bit.
+    pub fn create_opt(udp_payload_size: u16) -> Self {
+        Self::new(
+            String::new(),
+            QType::OPT,
+            QClass::from_u16(udp_payload_size),
+            0,
+            Box::new(OptRData::new(Vec::new())),
+        )
+    }
+
+    pub fn from_bytes(buf: &mut BytePacketBuffer) -> Result<Self, Box<dyn Error>> {
+        let qname = read_label(buf)?;
+        let rtype = QType::from_u16(buf.read_u16()?);
+
+        let class = QClass::from_u16(buf.read_u16()?);
+        let ttl = buf.read_u32()?;
+        let rdlength = buf.read_u16()?;
+
+        let rdata_start = buf.pos();
+        let rdata = read_rdata(rtype, buf, rdlength)?;
+
+        // A captured wire range is only safe to replay verbatim into another
+        // message if it can't contain a compression pointer aimed at *this*
+        // packet's offsets.
+        let raw = if rdata_embeds_a_name(rtype) {
+            let mut raw = Vec::new();
+            rdata.to_bytes(&mut raw);
+            raw
+        } else {
+            buf.range_at(rdata_start, rdlength as usize)?.to_vec()
         };
 
         Ok(Self {
@@ -186,14 +637,36 @@ impl Answer {
             rtype,
             class,
             ttl,
-            rdlength,
+            rdlength: raw.len() as u16,
             rdata,
+            raw,
         })
     }
+
+    /// Serializes NAME, TYPE, CLASS, TTL, RDLENGTH and RDATA, compressing
+    /// NAME against any domain names already written earlier in the message.
+    /// RDATA is written from `raw` rather than re-derived from `rdata`, so a
+    /// record round-trips byte-for-byte even where the typed representation
+    /// doesn't preserve every wire quirk (e.g. non-canonical TXT segmenting).
+    pub fn to_bytes(&self, buf: &mut Vec<u8>, compressor: &mut NameCompressor) {
+        compressor.write_name(buf, &self.name);
+        self.rtype.to_bytes(buf);
+        self.class.to_bytes(buf);
+        buf.extend_from_slice(&self.ttl.to_be_bytes());
+
+        write_u16(buf, self.raw.len() as u16);
+        buf.extend_from_slice(&self.raw);
+    }
 }
 
 impl fmt::Display for Answer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // OPT's CLASS/TTL don't hold a real class/TTL, so give it its own
+        // rendering instead of printing them as if they did.
+        if matches!(self.rtype, QType::OPT) {
+            return self.fmt_opt(f);
+        }
+
         writeln!(f, "NAME: {}", self.name)?;
         writeln!(f, "TYPE: {:?}", self.rtype)?;
         writeln!(f, "CLASS: {:?}", self.class)?;
@@ -204,3 +677,146 @@ impl fmt::Display for Answer {
         Ok(())
     }
 }
+
+impl Answer {
+    fn fmt_opt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let udp_payload_size = self.class.to_u16();
+        let extended_rcode = (self.ttl >> 24) as u8;
+        let version = (self.ttl >> 16) as u8;
+        let dnssec_ok = self.ttl >> 15 & 0x1 == 1;
+
+        writeln!(f, "TYPE: OPT (EDNS0)")?;
+        writeln!(f, "UDP PAYLOAD SIZE: {udp_payload_size}")?;
+        writeln!(f, "EXTENDED RCODE: {extended_rcode}")?;
+        writeln!(f, "VERSION: {version}")?;
+        writeln!(f, "DO: {}", dnssec_ok as u8)?;
+        write!(f, "OPTIONS: {}", self.rdata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rdata_of(rtype: QType, bytes: &[u8]) -> Box<dyn RData> {
+        let mut buf = BytePacketBuffer::new(bytes);
+
+        read_rdata(rtype, &mut buf, bytes.len() as u16).unwrap()
+    }
+
+    fn encode_name(name: &str) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_qname(&mut buf, name);
+        buf
+    }
+
+    #[test]
+    fn decodes_a_record() {
+        let rdata = rdata_of(QType::A, &[93, 184, 216, 34]);
+        assert_eq!(rdata.to_string(), "93.184.216.34");
+    }
+
+    #[test]
+    fn decodes_aaaa_record() {
+        let rdata = rdata_of(QType::AAAA, &Ipv6Addr::LOCALHOST.octets());
+        assert_eq!(rdata.to_string(), "::1");
+    }
+
+    #[test]
+    fn decodes_cname_record() {
+        let bytes = encode_name("www.example.com");
+        let rdata = rdata_of(QType::CNAME, &bytes);
+        assert_eq!(rdata.to_string(), "www.example.com.");
+    }
+
+    #[test]
+    fn decodes_ns_record() {
+        let bytes = encode_name("ns1.example.com");
+        let rdata = rdata_of(QType::NS, &bytes);
+        assert_eq!(rdata.to_string(), "ns1.example.com.");
+    }
+
+    #[test]
+    fn decodes_ptr_record() {
+        let bytes = encode_name("host.example.com");
+        let rdata = rdata_of(QType::PTR, &bytes);
+        assert_eq!(rdata.to_string(), "host.example.com.");
+    }
+
+    #[test]
+    fn decodes_mx_record() {
+        let mut bytes = vec![0, 10];
+        bytes.extend(encode_name("mail.example.com"));
+
+        let rdata = rdata_of(QType::MX, &bytes);
+        assert_eq!(rdata.to_string(), "mail.example.com. (10)");
+    }
+
+    #[test]
+    fn decodes_soa_record() {
+        let mut bytes = encode_name("ns1.example.com");
+        bytes.extend(encode_name("admin.example.com"));
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&2u32.to_be_bytes());
+        bytes.extend_from_slice(&3u32.to_be_bytes());
+        bytes.extend_from_slice(&4u32.to_be_bytes());
+        bytes.extend_from_slice(&5u32.to_be_bytes());
+
+        let rdata = rdata_of(QType::SOA, &bytes);
+        let text = rdata.to_string();
+
+        assert!(text.contains("MNAME: ns1.example.com."));
+        assert!(text.contains("SERIAL: 1"));
+        assert!(text.contains("MINIMUM: 5"));
+    }
+
+    #[test]
+    fn decodes_multi_segment_txt_record() {
+        let mut bytes = vec![5];
+        bytes.extend_from_slice(b"hello");
+        bytes.push(5);
+        bytes.extend_from_slice(b"world");
+
+        let rdata = rdata_of(QType::TXT, &bytes);
+        assert_eq!(rdata.to_string(), "hello world");
+    }
+
+    #[test]
+    fn decodes_srv_record() {
+        let mut bytes = vec![0, 1, 0, 2, 0x1F, 0x90];
+        bytes.extend(encode_name("node.example.com"));
+
+        let rdata = rdata_of(QType::SRV, &bytes);
+        assert_eq!(rdata.to_string(), "1 2 8080 node.example.com.");
+    }
+
+    #[test]
+    fn decodes_caa_record() {
+        let mut bytes = vec![0, 5];
+        bytes.extend_from_slice(b"issue");
+        bytes.extend_from_slice(b"ca.example.com");
+
+        let rdata = rdata_of(QType::CAA, &bytes);
+        assert_eq!(rdata.to_string(), "0 issue \"ca.example.com\"");
+    }
+
+    #[test]
+    fn answer_round_trips_rdata_bytes_on_the_wire() {
+        let a_bytes = [93, 184, 216, 34];
+        let mut packet = Vec::new();
+        write_qname(&mut packet, "example.com");
+        packet.extend_from_slice(&QType::A.to_u16().to_be_bytes());
+        packet.extend_from_slice(&QClass::IN.to_u16().to_be_bytes());
+        packet.extend_from_slice(&60u32.to_be_bytes());
+        packet.extend_from_slice(&(a_bytes.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&a_bytes);
+
+        let mut buf = BytePacketBuffer::new(&packet);
+        let answer = Answer::from_bytes(&mut buf).unwrap();
+
+        let mut out = Vec::new();
+        answer.to_bytes(&mut out, &mut NameCompressor::new());
+
+        assert_eq!(out, packet);
+    }
+}
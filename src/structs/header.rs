@@ -1,10 +1,71 @@
 use core::fmt;
 
-use super::write_u16;
+use super::{BytePacketBuffer, ParseError, write_u16};
 
-use bytes::Buf;
 use rand::Rng;
 
+/// The OPCODE field, decoded from its 4 bits. `Unknown` keeps the raw value
+/// around instead of panicking on one this crate doesn't model by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Query,
+    Status,
+    Notify,
+    Update,
+    Unknown(u8),
+}
+
+impl Opcode {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Query,
+            2 => Self::Status,
+            4 => Self::Notify,
+            5 => Self::Update,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// The RCODE field, decoded from its 4 bits. `Unknown` keeps the raw value
+/// around instead of panicking on one this crate doesn't model by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rcode {
+    NoError,
+    FormErr,
+    ServFail,
+    NXDomain,
+    NotImp,
+    Refused,
+    Unknown(u8),
+}
+
+impl Rcode {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::NoError,
+            1 => Self::FormErr,
+            2 => Self::ServFail,
+            3 => Self::NXDomain,
+            4 => Self::NotImp,
+            5 => Self::Refused,
+            other => Self::Unknown(other),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Self::NoError => 0,
+            Self::FormErr => 1,
+            Self::ServFail => 2,
+            Self::NXDomain => 3,
+            Self::NotImp => 4,
+            Self::Refused => 5,
+            Self::Unknown(value) => value,
+        }
+    }
+}
+
 pub struct Header {
     pub id: u16,
     flags: u16,
@@ -45,22 +106,76 @@ impl Header {
         write_u16(buf, self.arcount);
     }
 
-    pub fn from_bytes(buf: &mut &[u8]) -> Self {
-        let id = buf.get_u16();
-        let flags = buf.get_u16();
-        let qdcount = buf.get_u16();
-        let ancount = buf.get_u16();
-        let nscount = buf.get_u16();
-        let arcount = buf.get_u16();
+    /// Builds the header for a response to `query`: QR is set, OPCODE and
+    /// RD are mirrored from the query, RA is set (this crate always claims
+    /// to support recursion), and AA/RCODE are taken from the caller.
+    /// QDCOUNT is copied from the query; ANCOUNT, NSCOUNT and ARCOUNT start
+    /// at zero and the caller fills them in once it knows how many records
+    /// go in each section.
+    pub fn create_response_header(query: &Header, aa: bool, rcode: u8) -> Self {
+        let mut flags = 1 << 15; // QR (this is a response)
+        flags |= query.flags & (0xF << 11); // OPCODE
+        flags |= query.flags & (1 << 8); // RD
+        flags |= 1 << 7; // RA
+
+        if aa {
+            flags |= 1 << 10;
+        }
+
+        flags |= rcode as u16 & 0xF;
 
         Header {
+            id: query.id,
+            flags,
+            qdcount: query.qdcount,
+            ancount: 0,
+            nscount: 0,
+            arcount: 0,
+        }
+    }
+
+    pub fn set_rd(&mut self, recursion_desired: bool) {
+        self.set_flag(8, recursion_desired);
+    }
+
+    fn set_flag(&mut self, bit: u16, value: bool) {
+        if value {
+            self.flags |= 1 << bit;
+        } else {
+            self.flags &= !(1 << bit);
+        }
+    }
+
+    /// Whether the TC (truncation) bit is set, meaning the response was cut
+    /// short and should be re-requested over TCP.
+    pub fn is_truncated(&self) -> bool {
+        self.flags >> 9 & 0x1 == 1
+    }
+
+    pub fn opcode(&self) -> Opcode {
+        Opcode::from_u8((self.flags >> 11 & 0xF) as u8)
+    }
+
+    pub fn rcode(&self) -> Rcode {
+        Rcode::from_u8((self.flags & 0xF) as u8)
+    }
+
+    pub fn from_bytes(buf: &mut BytePacketBuffer) -> Result<Self, ParseError> {
+        let id = buf.read_u16()?;
+        let flags = buf.read_u16()?;
+        let qdcount = buf.read_u16()?;
+        let ancount = buf.read_u16()?;
+        let nscount = buf.read_u16()?;
+        let arcount = buf.read_u16()?;
+
+        Ok(Header {
             id,
             flags,
             qdcount,
             ancount,
             nscount,
             arcount,
-        }
+        })
     }
 }
 
@@ -69,13 +184,13 @@ impl fmt::Display for Header {
         writeln!(f, "ID: {}", self.id)?;
 
         writeln!(f, "QR: {}", self.flags >> 15)?;
-        writeln!(f, "OPCODE: {}", self.flags >> 11 & 0x7)?;
+        writeln!(f, "OPCODE: {:?}", self.opcode())?;
         writeln!(f, "AA: {}", self.flags >> 10 & 0x1)?;
         writeln!(f, "TC: {}", self.flags >> 9 & 0x1)?;
         writeln!(f, "RD: {}", self.flags >> 8 & 0x1)?;
         writeln!(f, "RA: {}", self.flags >> 7 & 0x1)?;
         writeln!(f, "Z: {}", self.flags >> 4 & 0x7)?;
-        writeln!(f, "RCODE: {}", self.flags & 0xF)?;
+        writeln!(f, "RCODE: {:?}", self.rcode())?;
 
         writeln!(f, "QDCOUNT: {}", self.qdcount)?;
         writeln!(f, "ANCOUNT: {}", self.ancount)?;
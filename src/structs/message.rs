@@ -1,7 +1,7 @@
 use core::fmt;
-use std::{collections::HashMap, error::Error};
+use std::error::Error;
 
-use crate::structs::{answer::Answer, header::Header, question::Question};
+use crate::structs::{BytePacketBuffer, NameCompressor, answer::Answer, header::Header, question::Question};
 
 pub struct Message {
     pub header: Header, // Always present
@@ -22,38 +22,91 @@ impl Message {
         })
     }
 
+    /// Builds a response to `query`: the question section is echoed back,
+    /// `answers` fill the answer section, and ANCOUNT/AA/RCODE are set
+    /// accordingly. Used by a `serve`-style authoritative responder.
+    pub fn create_response(query: &Message, answers: Vec<Answer>, authoritative: bool, rcode: u8) -> Self {
+        let mut header = Header::create_response_header(&query.header, authoritative, rcode);
+        header.ancount = answers.len() as u16;
+
+        Self {
+            header,
+            question: query.question.clone(),
+            answer: answers,
+            authority: Vec::new(),
+            additional: Vec::new(),
+        }
+    }
+
+    /// Appends an EDNS0 OPT pseudo-record to the additional section,
+    /// advertising `udp_payload_size` as the largest UDP response this
+    /// client can accept (servers otherwise assume the classic 512 bytes).
+    pub fn with_edns0(mut self, udp_payload_size: u16) -> Self {
+        self.additional.push(Answer::create_opt(udp_payload_size));
+        self.header.arcount += 1;
+        self
+    }
+
+    pub fn question(&self) -> &[Question] {
+        &self.question
+    }
+
+    pub fn answer(&self) -> &[Answer] {
+        &self.answer
+    }
+
+    pub fn authority(&self) -> &[Answer] {
+        &self.authority
+    }
+
+    pub fn additional(&self) -> &[Answer] {
+        &self.additional
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(512); // Max UDP Message size
+        let mut compressor = NameCompressor::new();
 
         self.header.to_bytes(&mut buf);
 
         for q in &self.question {
-            q.to_bytes(&mut buf);
+            q.to_bytes(&mut buf, &mut compressor);
+        }
+
+        for a in &self.answer {
+            a.to_bytes(&mut buf, &mut compressor);
+        }
+
+        for a in &self.authority {
+            a.to_bytes(&mut buf, &mut compressor);
+        }
+
+        for a in &self.additional {
+            a.to_bytes(&mut buf, &mut compressor);
         }
 
         buf
     }
 
     pub fn from_bytes(buf: &[u8], len: usize) -> Result<Self, Box<dyn Error>> {
-        let mut pointer = &buf[..len];
-        let mut nodes = HashMap::new();
+        let mut reader = BytePacketBuffer::new(&buf[..len]);
 
-        let header = Header::from_bytes(&mut pointer);
+        let header = Header::from_bytes(&mut reader)?;
 
         let question = (0..header.qdcount)
-            .map(|_| Question::from_bytes(&mut pointer, len, &mut nodes))
+            .map(|_| Question::from_bytes(&mut reader))
             .collect::<Result<Vec<_>, _>>()?;
 
         let answer = (0..header.ancount)
-            .map(|_| Answer::from_bytes(&mut pointer, len, &mut nodes))
+            .map(|_| Answer::from_bytes(&mut reader))
             .collect::<Result<Vec<_>, _>>()?;
 
         let authority = (0..header.nscount)
-            .map(|_| Answer::from_bytes(&mut pointer, len, &mut nodes))
+            .map(|_| Answer::from_bytes(&mut reader))
             .collect::<Result<Vec<_>, _>>()?;
 
         let additional = (0..header.arcount)
-            .map(|_| Answer::from_bytes(&mut pointer, len, &mut nodes))
+            .map(|_| Answer::from_bytes(&mut reader))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Message {
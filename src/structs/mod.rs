@@ -1,13 +1,11 @@
 use core::fmt;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
-
-use bytes::Buf;
+use std::collections::HashMap;
 
+pub mod answer;
 pub mod message;
+pub mod question;
 
-mod answer;
-mod header;
-mod question;
+pub(crate) mod header;
 
 #[derive(Debug)]
 pub struct ParseLabelError {
@@ -22,103 +20,248 @@ impl fmt::Display for ParseLabelError {
 
 impl std::error::Error for ParseLabelError {}
 
+/// A malformed or truncated packet, e.g. a read that runs past the end of
+/// the buffer. Parsing returns this instead of panicking so a hostile or
+/// corrupt response can't take the process down.
+#[derive(Debug)]
+pub struct ParseError {
+    pub value: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Malformed packet: {}", self.value)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 fn write_u16(buf: &mut Vec<u8>, v: u16) {
     buf.extend_from_slice(&v.to_be_bytes());
 }
 
-type RefNode = Rc<RefCell<Node>>;
+/// A bounds-checked cursor over a packet's bytes. Every read returns a
+/// `ParseError` instead of panicking once the buffer is exhausted, so
+/// truncated or hostile input surfaces as a recoverable `Err`.
+pub struct BytePacketBuffer<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BytePacketBuffer<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves the cursor to an absolute offset, e.g. to follow a compression
+    /// pointer. Bounds-checked like every other read so a pointer aimed past
+    /// the end of the packet surfaces as an error instead of a panic.
+    pub fn seek(&mut self, pos: usize) -> Result<(), ParseError> {
+        if pos > self.buf.len() {
+            return Err(ParseError {
+                value: "seek past end of packet".to_string(),
+            });
+        }
+
+        self.pos = pos;
+        Ok(())
+    }
+
+    /// Reads `len` bytes starting at an absolute offset without touching
+    /// the cursor. Used to recover the exact on-wire encoding of a section
+    /// whose end position is only known after parsing it (e.g. an RDATA
+    /// blob), and by compression-pointer following to peek ahead/behind the
+    /// current position.
+    pub fn range_at(&self, pos: usize, len: usize) -> Result<&'a [u8], ParseError> {
+        let end = pos.checked_add(len).ok_or(ParseError {
+            value: "read length overflowed".to_string(),
+        })?;
+
+        if end > self.buf.len() {
+            return Err(ParseError {
+                value: "unexpected end of packet".to_string(),
+            });
+        }
+
+        Ok(&self.buf[pos..end])
+    }
+
+    pub fn read_range(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        let range = self.range_at(self.pos, len)?;
+        self.pos += len;
+
+        Ok(range)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, ParseError> {
+        Ok(self.read_range(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, ParseError> {
+        let range = self.read_range(2)?;
+        Ok(u16::from_be_bytes([range[0], range[1]]))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, ParseError> {
+        let range = self.read_range(4)?;
+        Ok(u32::from_be_bytes([range[0], range[1], range[2], range[3]]))
+    }
+}
 
-struct Node {
-    label: String,
-    next: Option<RefNode>,
+/// Maintains a suffix-to-offset map of domain names already written to a
+/// message buffer so subsequent names (or their suffixes) can be emitted as
+/// an RFC 1035 compression pointer instead of re-encoding the labels.
+pub struct NameCompressor {
+    offsets: HashMap<String, u16>,
 }
 
-impl Node {
-    fn new(label: String) -> RefNode {
-        Rc::new(RefCell::new(Node { label, next: None }))
+impl NameCompressor {
+    pub fn new() -> Self {
+        Self {
+            offsets: HashMap::new(),
+        }
     }
 
-    fn get_full_label(&self) -> String {
-        let mut l = self.label.clone();
-        l.push('.');
+    pub fn write_name(&mut self, buf: &mut Vec<u8>, name: &str) {
+        let labels: Vec<&str> = name
+            .trim_end_matches('.')
+            .split('.')
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        for i in 0..labels.len() {
+            let suffix = labels[i..].join(".");
+
+            if let Some(&offset) = self.offsets.get(&suffix) {
+                buf.push(0xC0 | (offset >> 8) as u8);
+                buf.push((offset & 0xFF) as u8);
+                return;
+            }
+
+            // Pointers can only address the first 16384 bytes of a message.
+            if u16::try_from(buf.len()).is_ok_and(|offset| offset <= 0x3FFF) {
+                self.offsets.insert(suffix, buf.len() as u16);
+            }
 
-        if self.next.is_none() {
-            return l.to_string();
+            buf.push(labels[i].len() as u8);
+            buf.extend_from_slice(labels[i].as_bytes());
         }
 
-        l + &self.next.as_ref().unwrap().borrow().get_full_label()
+        buf.push(0);
+    }
+}
+
+impl Default for NameCompressor {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-fn read_label(
-    buf: &mut &[u8],
-    mut index: usize,
-    nodes: &mut HashMap<usize, RefNode>,
-) -> Result<String, ParseLabelError> {
-    let mut head: Option<RefNode> = None;
-    let mut prev: Option<RefNode> = None;
+/// A pointer that only ever jumped backwards would still need a cap to
+/// protect against a very long chain of distinct pointers; this bounds the
+/// number of jumps a single name can take regardless of direction.
+const MAX_POINTER_JUMPS: usize = 20;
+
+/// Reads a (possibly compressed) domain name starting at the buffer's
+/// current position. Compression pointers are followed against absolute
+/// message offsets, capped at `MAX_POINTER_JUMPS` jumps so a pointer cycle
+/// can't loop forever, and the buffer's cursor ends up just past the name's
+/// own encoding (its labels, or the two bytes of the pointer that replaced
+/// them) regardless of how many pointers were followed to resolve it.
+fn read_label(buf: &mut BytePacketBuffer) -> Result<String, ParseLabelError> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut pos = buf.pos();
+    let mut resume_at: Option<usize> = None;
+    let mut jumps = 0;
 
     loop {
-        let octet = buf.get_u8();
-
-        let comp_bits = octet & 0xC0; // (first 2 bits)
-        if comp_bits == 0xC0 {
-            let upper = octet & 0x3F;
-            let lower = buf.get_u8();
-
-            let offset = ((upper as u16) << 8 | lower as u16) as usize;
-            match nodes.get(&offset) {
-                Some(node) => {
-                    if let Some(p) = prev {
-                        p.borrow_mut().next = Some(node.clone());
-                    }
-
-                    if head.is_none() {
-                        head = Some(node.clone());
-                    }
-                }
-
-                None => {
-                    return Err(ParseLabelError {
-                        value: "No entry on nodes".to_string(),
-                    });
-                }
+        let octet = read_u8_at(buf, pos)?;
+
+        if octet & 0xC0 == 0xC0 {
+            if jumps >= MAX_POINTER_JUMPS {
+                return Err(ParseLabelError {
+                    value: "too many compression pointer jumps".to_string(),
+                });
             }
 
-            break;
+            let lower = read_u8_at(buf, pos + 1)?;
+            let offset = (((octet & 0x3F) as usize) << 8) | lower as usize;
+
+            // Only the position right after the *first* pointer matters:
+            // that's where the caller's read of this name actually ends.
+            resume_at.get_or_insert(pos + 2);
+
+            pos = offset;
+            jumps += 1;
+            continue;
         }
 
-        let len = octet & 0x3F; // (last 6 bits)
-        // Terminate with 0
+        let len = (octet & 0x3F) as usize;
+
         if len == 0 {
+            pos += 1;
             break;
         }
 
-        let label = (0..len).map(|_| buf.get_u8() as char).collect();
+        let label = buf
+            .range_at(pos + 1, len)
+            .map_err(|e| ParseLabelError { value: e.value })?
+            .iter()
+            .map(|&b| b as char)
+            .collect();
 
-        let node = Node::new(label);
+        labels.push(label);
+        pos += 1 + len;
+    }
 
-        nodes.insert(index, node.clone());
+    buf.seek(resume_at.unwrap_or(pos))
+        .map_err(|e| ParseLabelError { value: e.value })?;
 
-        if head.is_none() {
-            head = Some(node.clone());
-        }
+    if labels.is_empty() {
+        return Ok(String::new());
+    }
 
-        if let Some(p) = prev {
-            p.borrow_mut().next = Some(node.clone());
-        }
+    let mut name = labels.join(".");
+    name.push('.');
 
-        prev = Some(node.clone());
+    Ok(name)
+}
 
-        // Include first octet
-        index += 1 + len as usize
-    }
+fn read_u8_at(buf: &BytePacketBuffer, pos: usize) -> Result<u8, ParseLabelError> {
+    buf.range_at(pos, 1)
+        .map(|r| r[0])
+        .map_err(|e| ParseLabelError { value: e.value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_label_follows_a_compression_pointer() {
+        // offset 0: "example" + pointer to offset 10
+        // offset 10: "com" + terminator
+        let packet = [7u8, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0xC0, 10, 3, b'c', b'o', b'm', 0];
+
+        let mut buf = BytePacketBuffer::new(&packet);
+        let name = read_label(&mut buf).unwrap();
 
-    if head.is_none() {
-        return Err(ParseLabelError {
-            value: "No head detected".to_string(),
-        });
+        assert_eq!(name, "example.com.");
+        assert_eq!(buf.pos(), 10); // just past the 2-byte pointer, not offset 9
     }
 
-    Ok(head.unwrap().borrow().get_full_label())
+    #[test]
+    fn read_label_rejects_a_pointer_cycle() {
+        // offset 0 points to offset 2, which points right back to offset 0.
+        let packet = [0xC0u8, 2, 0xC0, 0];
+
+        let mut buf = BytePacketBuffer::new(&packet);
+        let err = read_label(&mut buf).unwrap_err();
+
+        assert!(err.value.contains("too many"));
+    }
 }
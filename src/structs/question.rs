@@ -1,11 +1,9 @@
 use core::fmt;
-use std::{collections::HashMap, error::Error, str::FromStr};
+use std::{error::Error, str::FromStr};
 
-use crate::structs::{RefNode, read_label};
+use crate::structs::read_label;
 
-use super::write_u16;
-
-use bytes::Buf;
+use super::{BytePacketBuffer, NameCompressor, write_u16};
 
 #[derive(Debug)]
 pub struct ParseQTypeError {
@@ -33,29 +31,42 @@ impl fmt::Display for ParseQClassError {
 
 impl std::error::Error for ParseQClassError {}
 
-#[repr(u16)]
 #[derive(Debug, Clone, Copy)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum QType {
-    A = 1,
-    NS = 2,
-    CNAME = 5,
-    MX = 15,
-    TXT = 16,
-    AAAA = 28,
+    A,
+    NS,
+    CNAME,
+    SOA,
+    PTR,
+    MX,
+    TXT,
+    AAAA,
+    SRV,
+    CAA,
+    /// The EDNS0 pseudo-record (RFC 6891). CLASS and TTL are repurposed to
+    /// carry the UDP payload size and extended flags instead of a real
+    /// class/TTL, so it's handled separately wherever that matters.
+    OPT,
+    /// Any record type this crate doesn't model yet, keyed by its numeric code.
+    Unknown(u16),
 }
 
-#[repr(u16)]
 #[derive(Debug, Clone, Copy)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum QClass {
-    IN = 1,
-    CS = 2,
-    CH = 3,
-    HS = 4,
-    ANY = 255,
+    IN,
+    CS,
+    CH,
+    HS,
+    ANY,
+    /// Any class value this crate doesn't model yet, keyed by its numeric
+    /// code. Also covers the EDNS0 OPT record, whose CLASS field is really
+    /// the requestor's UDP payload size rather than a class.
+    Unknown(u16),
 }
 
+#[derive(Clone)]
 pub struct Question {
     qname: String,
     qtype: QType,
@@ -70,9 +81,14 @@ impl FromStr for QType {
             "A" => Ok(Self::A),
             "NS" => Ok(Self::NS),
             "CNAME" => Ok(Self::CNAME),
+            "SOA" => Ok(Self::SOA),
+            "PTR" => Ok(Self::PTR),
             "MX" => Ok(Self::MX),
             "TXT" => Ok(Self::TXT),
             "AAAA" => Ok(Self::AAAA),
+            "SRV" => Ok(Self::SRV),
+            "CAA" => Ok(Self::CAA),
+            "OPT" => Ok(Self::OPT),
             _ => Err(ParseQTypeError {
                 value: s.to_string(),
             }),
@@ -81,22 +97,46 @@ impl FromStr for QType {
 }
 
 impl QType {
-    pub fn from_u16(value: u16) -> Result<Self, ParseQTypeError> {
+    /// Unlike `from_str`, this never fails: a code we
+    /// don't model yet is kept around as `Unknown` instead of erroring out, so
+    /// a response containing a record type we don't understand can still be
+    /// read (and its RDATA surfaced raw) rather than rejecting the message.
+    pub fn from_u16(value: u16) -> Self {
         match value {
-            1 => Ok(Self::A),
-            2 => Ok(Self::NS),
-            5 => Ok(Self::CNAME),
-            15 => Ok(Self::MX),
-            16 => Ok(Self::TXT),
-            28 => Ok(Self::AAAA),
-            _ => Err(ParseQTypeError {
-                value: value.to_string(),
-            }),
+            1 => Self::A,
+            2 => Self::NS,
+            5 => Self::CNAME,
+            6 => Self::SOA,
+            12 => Self::PTR,
+            15 => Self::MX,
+            16 => Self::TXT,
+            28 => Self::AAAA,
+            33 => Self::SRV,
+            41 => Self::OPT,
+            257 => Self::CAA,
+            other => Self::Unknown(other),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        match self {
+            Self::A => 1,
+            Self::NS => 2,
+            Self::CNAME => 5,
+            Self::SOA => 6,
+            Self::PTR => 12,
+            Self::MX => 15,
+            Self::TXT => 16,
+            Self::AAAA => 28,
+            Self::SRV => 33,
+            Self::OPT => 41,
+            Self::CAA => 257,
+            Self::Unknown(value) => value,
         }
     }
 
     pub fn to_bytes(self, buf: &mut Vec<u8>) {
-        write_u16(buf, self as u16);
+        write_u16(buf, self.to_u16());
     }
 }
 
@@ -118,21 +158,33 @@ impl FromStr for QClass {
 }
 
 impl QClass {
-    pub fn from_u16(value: u16) -> Result<Self, ParseQClassError> {
+    /// Like `QType::from_u16`, this never fails: a code we don't model yet
+    /// (including the UDP payload size an OPT record's CLASS field really
+    /// holds) is kept around as `Unknown` instead of erroring out.
+    pub fn from_u16(value: u16) -> Self {
         match value {
-            1 => Ok(Self::IN),
-            2 => Ok(Self::CS),
-            3 => Ok(Self::CH),
-            4 => Ok(Self::HS),
-            255 => Ok(Self::ANY),
-            _ => Err(ParseQClassError {
-                value: value.to_string(),
-            }),
+            1 => Self::IN,
+            2 => Self::CS,
+            3 => Self::CH,
+            4 => Self::HS,
+            255 => Self::ANY,
+            other => Self::Unknown(other),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        match self {
+            Self::IN => 1,
+            Self::CS => 2,
+            Self::CH => 3,
+            Self::HS => 4,
+            Self::ANY => 255,
+            Self::Unknown(value) => value,
         }
     }
 
     pub fn to_bytes(self, buf: &mut Vec<u8>) {
-        write_u16(buf, self as u16);
+        write_u16(buf, self.to_u16());
     }
 }
 
@@ -162,28 +214,21 @@ impl Question {
     // e.g.
     // [  6  ]['t' 'o' 'm' 'a' 's' 'e']
     // [  0  ]
-    pub fn to_bytes(&self, buf: &mut Vec<u8>) {
-        for label in self.qname.split('.') {
-            buf.push(label.len() as u8);
-            buf.extend_from_slice(label.as_bytes());
-        }
-
-        buf.push(0); // terminator
+    //
+    // `compressor` lets the qname (or a suffix of it) be written as a
+    // pointer when it's a repeat of a name already written elsewhere in
+    // the message.
+    pub fn to_bytes(&self, buf: &mut Vec<u8>, compressor: &mut NameCompressor) {
+        compressor.write_name(buf, &self.qname);
 
         self.qtype.to_bytes(buf);
         self.qclass.to_bytes(buf);
     }
 
-    pub fn from_bytes(
-        buf: &mut &[u8],
-        len: usize,
-        nodes: &mut HashMap<usize, RefNode>,
-    ) -> Result<Self, Box<dyn Error>> {
-        let index = len - buf.remaining();
-
-        let qname = read_label(buf, index, nodes)?;
-        let qtype = QType::from_u16(buf.get_u16())?;
-        let qclass = QClass::from_u16(buf.get_u16())?;
+    pub fn from_bytes(buf: &mut BytePacketBuffer) -> Result<Self, Box<dyn Error>> {
+        let qname = read_label(buf)?;
+        let qtype = QType::from_u16(buf.read_u16()?);
+        let qclass = QClass::from_u16(buf.read_u16()?);
 
         Ok(Self {
             qname,
@@ -191,6 +236,14 @@ impl Question {
             qclass,
         })
     }
+
+    pub fn qname(&self) -> &str {
+        &self.qname
+    }
+
+    pub fn qtype(&self) -> QType {
+        self.qtype
+    }
 }
 
 impl fmt::Display for Question {
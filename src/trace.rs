@@ -0,0 +1,163 @@
+use std::{
+    error::Error,
+    net::{Ipv4Addr, SocketAddr},
+    str::FromStr,
+};
+
+use crate::{
+    resolver::Resolver,
+    structs::{
+        answer::{Answer, DomainNameRData, IpRData},
+        header::Rcode,
+        message::Message,
+        question::QType,
+    },
+};
+
+/// A handful of the real root servers, enough to get an iterative lookup
+/// started without having to ship (and keep up to date) the full root hints
+/// file.
+const ROOT_SERVERS: [Ipv4Addr; 4] = [
+    Ipv4Addr::new(198, 41, 0, 4),   // a.root-servers.net
+    Ipv4Addr::new(199, 9, 14, 201), // b.root-servers.net
+    Ipv4Addr::new(192, 33, 4, 12),  // c.root-servers.net
+    Ipv4Addr::new(192, 5, 5, 241),  // d.root-servers.net
+];
+
+/// Bounds how many queries a single trace loop sends while following NS
+/// referrals and CNAME hops (one shared count for both), so a misconfigured
+/// or malicious zone can't send it looping forever.
+const MAX_REFERRALS: u32 = 20;
+
+/// Resolves `domain`/`qtype`/`qclass` the way `dig +trace` does: starting
+/// from the root servers, with RD cleared, following NS referrals down the
+/// delegation chain (using additional-section glue where present, otherwise
+/// resolving the nameserver's own address first) and CNAME chains, until an
+/// answer or an authoritative NXDOMAIN is reached.
+pub fn trace(domain: &str, qtype: &str, qclass: &str) -> Result<Message, Box<dyn Error>> {
+    let mut domain = domain.to_string();
+    // `None` means "query the root": rather than pinning a single hard-coded
+    // root server, `query_root` tries each one in turn until one answers.
+    let mut server: Option<Ipv4Addr> = None;
+    let mut referrals = 0;
+
+    let wanted = QType::from_str(qtype)?;
+
+    loop {
+        if referrals >= MAX_REFERRALS {
+            return Err("too many referrals while tracing".into());
+        }
+        referrals += 1;
+
+        let mut query = Message::create_query(&domain, qtype, qclass)?;
+        query.header.set_rd(false);
+
+        let response = match server {
+            Some(addr) => Resolver::new().query(SocketAddr::from((addr, 53)), &query)?,
+            None => query_root(&query)?,
+        };
+
+        let rcode = response.header.rcode();
+        if rcode != Rcode::NoError {
+            return Err(format!("server returned {rcode:?}").into());
+        }
+
+        if response
+            .answer()
+            .iter()
+            .any(|a| a.rtype().to_u16() == wanted.to_u16())
+        {
+            return Ok(response);
+        }
+
+        if let Some(cname) = response
+            .answer()
+            .iter()
+            .find(|a| matches!(a.rtype(), QType::CNAME))
+        {
+            let target = cname_target(cname)?;
+            domain = target;
+            server = None;
+            continue;
+        }
+
+        let Some(ns_name) = response
+            .authority()
+            .iter()
+            .find(|a| matches!(a.rtype(), QType::NS))
+            .and_then(ns_target)
+        else {
+            // No NS referral and no answer: the server is authoritative for
+            // this name and simply has no record of the requested type
+            // (an NXDOMAIN never gets here: it is returned as an error above).
+            return Ok(response);
+        };
+
+        server = Some(match glue_address(&response, &ns_name) {
+            Some(addr) => addr,
+            None => {
+                let resolved = trace(&ns_name, "A", qclass)?;
+                let Some(addr) = resolved.answer().iter().find_map(a_record_address) else {
+                    return Err(format!("could not resolve nameserver {ns_name}").into());
+                };
+                addr
+            }
+        });
+    }
+}
+
+/// Queries each root server in turn, returning the first response received.
+/// A single unreachable root (the common failure mode for a hard-coded IP
+/// going stale) shouldn't sink the whole trace.
+fn query_root(query: &Message) -> Result<Message, Box<dyn Error>> {
+    let mut last_err: Option<Box<dyn Error>> = None;
+
+    for &root in &ROOT_SERVERS {
+        match Resolver::new().query(SocketAddr::from((root, 53)), query) {
+            Ok(response) => return Ok(response),
+            Err(e) => last_err = Some(e),
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| "no root servers configured".into()))
+}
+
+fn cname_target(answer: &Answer) -> Result<String, Box<dyn Error>> {
+    answer
+        .rdata()
+        .as_any()
+        .downcast_ref::<DomainNameRData>()
+        .map(|rdata| rdata.name().to_string())
+        .ok_or_else(|| "CNAME record had unexpected RDATA".into())
+}
+
+fn ns_target(answer: &Answer) -> Option<String> {
+    answer
+        .rdata()
+        .as_any()
+        .downcast_ref::<DomainNameRData>()
+        .map(|rdata| rdata.name().to_string())
+}
+
+fn a_record_address(answer: &Answer) -> Option<Ipv4Addr> {
+    if !matches!(answer.rtype(), QType::A) {
+        return None;
+    }
+
+    answer
+        .rdata()
+        .as_any()
+        .downcast_ref::<IpRData>()
+        .map(|rdata| rdata.addr())
+}
+
+/// Looks for A-record glue for `ns_name` in the additional section, so the
+/// nameserver's address doesn't need a separate lookup when the referring
+/// server was kind enough to include it.
+fn glue_address(response: &Message, ns_name: &str) -> Option<Ipv4Addr> {
+    response
+        .additional()
+        .iter()
+        .filter(|a| a.name() == ns_name)
+        .find_map(a_record_address)
+}